@@ -1,6 +1,6 @@
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 pub type BranchRef = Rc<RefCell<Branch>>;
 
@@ -23,6 +23,97 @@ impl Branch {
     pub fn inherits_from(&mut self, child: &BranchRef) {
         self.inherits.push(child.clone());
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn inherits(&self) -> &[BranchRef] {
+        &self.inherits
+    }
+
+    /// Replaces this branch's `inherits` list wholesale.
+    pub fn set_inherits(&mut self, inherits: Vec<BranchRef>) {
+        self.inherits = inherits;
+    }
+
+    /// Returns the complete transitive set of branches this branch inherits
+    /// from, de-duplicated and in a stable order: a branch's direct parents
+    /// come before their own parents, so that in a diamond (`a` inherits
+    /// `b` and `c`, both inheriting `d`), `d` is listed once, after both
+    /// `b` and `c`.
+    pub fn resolved_ancestors(&self) -> Vec<BranchRef> {
+        let mut seen = HashSet::new();
+        let mut queue: VecDeque<BranchRef> = self.inherits.iter().cloned().collect();
+        let mut result = Vec::new();
+
+        while let Some(branch) = queue.pop_front() {
+            let name = branch.borrow().name().to_string();
+            if !seen.insert(name) {
+                continue;
+            }
+            for parent in branch.borrow().inherits() {
+                queue.push_back(parent.clone());
+            }
+            result.push(branch);
+        }
+
+        result
+    }
+}
+
+// }}}
+// {{{ Tests
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn branch(name: &str) -> BranchRef {
+        Rc::new(RefCell::new(Branch::new(name.to_string())))
+    }
+
+    fn names(branches: &[BranchRef]) -> Vec<String> {
+        branches.iter().map(|b| b.borrow().name().to_string()).collect()
+    }
+
+    #[test]
+    fn test_resolved_ancestors_diamond() {
+        let d = branch("d");
+        let b = branch("b");
+        let c = branch("c");
+        let a = branch("a");
+
+        b.borrow_mut().inherits_from(&d);
+        c.borrow_mut().inherits_from(&d);
+        a.borrow_mut().inherits_from(&b);
+        a.borrow_mut().inherits_from(&c);
+
+        assert_eq!(names(&a.borrow().resolved_ancestors()),
+                   vec!["b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_resolved_ancestors_deep_chain() {
+        let d = branch("d");
+        let c = branch("c");
+        let b = branch("b");
+        let a = branch("a");
+
+        b.borrow_mut().inherits_from(&c);
+        c.borrow_mut().inherits_from(&d);
+        a.borrow_mut().inherits_from(&b);
+
+        assert_eq!(names(&a.borrow().resolved_ancestors()),
+                   vec!["b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_resolved_ancestors_no_parents() {
+        let a = branch("a");
+
+        assert!(a.borrow().resolved_ancestors().is_empty());
+    }
 }
 
 // }}}
@@ -50,6 +141,14 @@ impl Repo {
     pub fn find_branch(&self, branch_name: &str) -> Option<&BranchRef> {
         self.branches.get(branch_name)
     }
+
+    pub fn branches(&self) -> &HashMap<String, BranchRef> {
+        &self.branches
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 // }}}
@@ -70,6 +169,18 @@ impl Context {
     pub fn add_repo(&mut self, repo: Repo) {
         self.repos.insert(repo.name.to_string(), repo);
     }
+
+    pub fn find_repo(&self, repo_name: &str) -> Option<&Repo> {
+        self.repos.get(repo_name)
+    }
+
+    pub fn find_repo_mut(&mut self, repo_name: &str) -> Option<&mut Repo> {
+        self.repos.get_mut(repo_name)
+    }
+
+    pub fn repos(&self) -> &HashMap<String, Repo> {
+        &self.repos
+    }
 }
 
 // }}}
@@ -1,169 +1,307 @@
-extern crate toml;
+extern crate serde_derive;
+
+use self::serde_derive::Deserialize;
 
 use core::Error;
 
 use git::{Branch, Repo, Context};
+use source::{self, Value};
 
 use std::collections::HashMap;
-use std::fs;
-use std::io::Read;
+use std::env;
 
 // {{{ Helpers
 
-macro_rules! try_toml {
-    ($expr:expr, $err:expr) => (match $expr {
-        Some(t) => t,
-        None => return Err(Error::TomlError($err.to_string())),
-    })
-}
-
 macro_rules! throw_err {
     ($($arg:tt)*) => (
         return Err(Error::StructuralError(format!($($arg)*)));
     )
 }
 
-trait FromToml {
-    fn from_toml(&toml::Table) -> Result<Self, Error>;
-}
-
 // }}}
-// {{{ TOML tables to structures
+// {{{ Values to structures
 // {{{ Branch
 
-#[derive(Debug)]
+#[derive(Debug, Deserialize)]
 pub struct ParsedBranch {
     pub name: String,
+    #[serde(default)]
     pub inherits: Vec<String>,
 }
 
-impl FromToml for ParsedBranch {
-    fn from_toml(table: &toml::Table) -> Result<ParsedBranch, Error> {
-        let name = try_toml!(table.get("name").and_then(|v| v.as_str()),
-                             "table `branch` should have a `name` attribute");
-
-        let mut branch = ParsedBranch {
-            name: name.to_string(),
-            inherits: Vec::new(),
-        };
-        match table.get("inherits") {
-            None => Ok(branch),
-            Some(v) => {
-                let branches = try_toml!(v.as_slice(),
-                                      "value `inherits` should be an array");
-                for b in branches {
-                    let s = try_toml!(b.as_str(),
-                                      "`inherits` values should be strings");
-                    branch.inherits.push(s.to_string());
-                }
-                Ok(branch)
-            }
-        }
-    }
-}
-
 // }}}
 // {{{ Repo
 
+#[derive(Debug, Deserialize)]
+struct RawRepo {
+    name: String,
+    branch: Vec<ParsedBranch>,
+}
+
 #[derive(Debug)]
 pub struct ParsedRepo {
     pub name: String,
     pub branches: HashMap<String, ParsedBranch>,
 }
 
-impl FromToml for ParsedRepo {
-    fn from_toml(table: &toml::Table) -> Result<ParsedRepo, Error> {
-        let name = try_toml!(table.get("name").and_then(|v| v.as_str()),
-                             "table `repo` should have a `name` attribute");
-
-        let branches = try_toml!(table.get("branch")
-                                      .and_then(|v| v.as_slice()),
-                                 "table `repo` should have an array \
-                                  `branch`");
-        let mut repo = ParsedRepo {
-            name: name.to_string(),
-            branches: HashMap::new(),
-        };
-        for branch in branches {
-            let brc_table = try_toml!(branch.as_table(),
-                                      "value `branch` should be a table");
-            let b = try!(ParsedBranch::from_toml(brc_table));
+impl From<RawRepo> for ParsedRepo {
+    fn from(raw: RawRepo) -> ParsedRepo {
+        let mut branches = HashMap::new();
+        for branch in raw.branch {
+            branches.insert(branch.name.to_string(), branch);
+        }
 
-            repo.branches.insert(b.name.to_string(), b);
+        ParsedRepo {
+            name: raw.name,
+            branches: branches,
         }
+    }
+}
+
+/// Wires a deserialized `ParsedRepo` into a `git::Repo`: resolves
+/// `inherits` names into `BranchRef` edges, rejects self-inheritance and
+/// detects cycles. These are the structural rules serde's derive can't
+/// express on its own.
+fn build_repo(parsed: ParsedRepo) -> Result<Repo, Error> {
+    let mut repo = Repo::new(parsed.name.to_string());
 
-        Ok(repo)
+    // create Branch objects for each parsed branch
+    for name in parsed.branches.keys() {
+        repo.add_branch(Branch::new(name.to_string()));
+    }
+
+    // add parents for each branch
+    for parsed_branch in parsed.branches.values() {
+        let mut child = repo.find_branch(&parsed_branch.name)
+                            .unwrap().borrow_mut();
+
+        for parent_name in &parsed_branch.inherits {
+            if parent_name == &parsed_branch.name {
+                throw_err!("branch `{}` in repo `{}` cannot inherit \
+                            from itself", parent_name, parsed.name);
+            }
+            match repo.find_branch(parent_name) {
+                Some(parent_branch) => child.inherits_from(parent_branch),
+                None => throw_err!("unknown branch `{}` in repo `{}`",
+                                   parent_name, parsed.name),
+            }
+        }
     }
+
+    try!(check_inheritance_cycles(&repo, &parsed.name));
+
+    Ok(repo)
 }
 
-impl FromToml for Repo {
-    fn from_toml(table: &toml::Table) -> Result<Repo, Error> {
-        let parsed = try!(ParsedRepo::from_toml(table));
-        let mut repo = Repo::new(parsed.name.to_string());
+// }}}
+// {{{ Cycle detection
 
-        // create Branch objects for each parsed branch
-        for name in parsed.branches.keys() {
-            repo.add_branch(Branch::new(name.to_string()));
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Color {
+    White,
+    Gray,
+    Black,
+}
+
+/// Walks the inheritance graph of `repo` with a three-color DFS, erroring
+/// out as soon as a back-edge (a branch reachable from itself through
+/// `inherits`) is found.
+fn check_inheritance_cycles(repo: &Repo, repo_name: &str) -> Result<(), Error> {
+    let mut colors: HashMap<String, Color> = repo.branches()
+        .keys()
+        .map(|name| (name.to_string(), Color::White))
+        .collect();
+
+    let mut names: Vec<String> = repo.branches().keys().cloned().collect();
+    names.sort();
+    for name in &names {
+        if colors.get(name) == Some(&Color::White) {
+            let mut path = Vec::new();
+            try!(visit_branch(repo, name, repo_name, &mut colors, &mut path));
         }
+    }
 
-        // add parents for each branch
-        for parsed_branch in parsed.branches.values() {
-            let mut child = repo.find_branch(&parsed_branch.name)
-                                .unwrap().borrow_mut();
+    Ok(())
+}
 
-            for parent_name in &parsed_branch.inherits {
-                if parent_name == &parsed_branch.name {
-                    throw_err!("branch `{}` in repo `{}` cannot inherit \
-                                from itself", parent_name, parsed.name);
-                }
-                match repo.find_branch(parent_name) {
-                    Some(parent_branch) => child.inherits_from(parent_branch),
-                    None => throw_err!("unknown branch `{}` in repo `{}`",
-                                       parent_name, parsed.name),
-                }
+fn visit_branch(repo: &Repo, name: &str, repo_name: &str,
+                 colors: &mut HashMap<String, Color>,
+                 path: &mut Vec<String>) -> Result<(), Error> {
+    colors.insert(name.to_string(), Color::Gray);
+    path.push(name.to_string());
+
+    let branch = repo.find_branch(name).unwrap().borrow();
+    for parent in branch.inherits() {
+        let parent_name = parent.borrow().name().to_string();
+
+        match colors.get(&parent_name).cloned() {
+            Some(Color::Gray) => {
+                let start = path.iter().position(|n| n == &parent_name).unwrap();
+                let mut cycle = path[start..].to_vec();
+                cycle.push(parent_name);
+                throw_err!("inheritance cycle in repo `{}`: {}",
+                           repo_name, cycle.join(" -> "));
             }
+            Some(Color::White) => {
+                try!(visit_branch(repo, &parent_name, repo_name, colors, path));
+            }
+            _ => {}
         }
-
-        Ok(repo)
     }
+
+    path.pop();
+    colors.insert(name.to_string(), Color::Black);
+
+    Ok(())
 }
 
 // }}}
 // {{{ Context
 
-impl FromToml for Context {
-    fn from_toml(table: &toml::Table) -> Result<Context, Error> {
-        let repos = try_toml!(table.get("repo").and_then(|v| v.as_slice()),
-                              "value `repo` should be an array");
-        let mut ctx = Context::new();
+#[derive(Debug, Deserialize)]
+struct RawContext {
+    repo: Vec<RawRepo>,
+}
 
-        for repo in repos {
-            let repo_table = try_toml!(repo.as_table(),
-                                       "value `repo` should be a table");
-            let r = try!(Repo::from_toml(repo_table));
+fn build_context(raw: RawContext) -> Result<Context, Error> {
+    let mut ctx = Context::new();
 
-            ctx.add_repo(r);
-        }
+    for raw_repo in raw.repo {
+        let repo = try!(build_repo(ParsedRepo::from(raw_repo)));
+        ctx.add_repo(repo);
+    }
 
-        Ok(ctx)
+    Ok(ctx)
+}
+
+impl Context {
+    fn from_value(value: Value) -> Result<Context, Error> {
+        let raw: RawContext = try!(source::from_value(value));
+
+        build_context(raw)
     }
 }
 
 // }}}
+// }}}
+// {{{ Env overrides
+
+/// Applies `SCOUT_REPO_<repo>_BRANCH_<branch>_INHERITS=a,b,c`-style
+/// environment variables on top of an already-parsed `Context`, creating
+/// any branch named in the variable that doesn't exist yet. A variable
+/// naming a repo that isn't in the `Context` is an error.
+fn apply_env_overrides(ctx: &mut Context, prefix: &str) -> Result<(), Error> {
+    let repo_marker = format!("{}REPO_", prefix);
+    let branch_marker = "_BRANCH_";
+    let inherits_marker = "_INHERITS";
+
+    for (key, value) in env::vars() {
+        if !key.starts_with(&repo_marker) {
+            continue;
+        }
+        let rest = &key[repo_marker.len()..];
+
+        let branch_pos = match rest.find(branch_marker) {
+            Some(pos) => pos,
+            None => continue,
+        };
+        let repo_name = &rest[..branch_pos];
+
+        let rest = &rest[branch_pos + branch_marker.len()..];
+        if !rest.ends_with(inherits_marker) {
+            continue;
+        }
+        let branch_name = &rest[..rest.len() - inherits_marker.len()];
+
+        let repo = match ctx.find_repo_mut(repo_name) {
+            Some(r) => r,
+            None => throw_err!("unknown repo `{}` in environment override `{}`",
+                               repo_name, key),
+        };
+
+        if repo.find_branch(branch_name).is_none() {
+            repo.add_branch(Branch::new(branch_name.to_string()));
+        }
+
+        let mut inherits = Vec::new();
+        for parent_name in value.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            if repo.find_branch(parent_name).is_none() {
+                repo.add_branch(Branch::new(parent_name.to_string()));
+            }
+            inherits.push(repo.find_branch(parent_name).unwrap().clone());
+        }
+
+        repo.find_branch(branch_name).unwrap().borrow_mut().set_inherits(inherits);
+    }
+
+    Ok(())
+}
+
 // }}}
 
 impl Context {
     pub fn from_config(cfgfile_path: &str) -> Result<Context, Error> {
-        let mut f = fs::File::open(cfgfile_path).unwrap();
-        let mut buf = String::new();
-        f.read_to_string(&mut buf).unwrap();
+        Context::from_config_namespaced(cfgfile_path, None)
+    }
 
-        let mut parser = toml::Parser::new(&buf);
-        let table = try_toml!(parser.parse(),
-                              format!("error while parsing `{}`: {:?}",
-                                      &cfgfile_path, parser.errors));
+    /// Like `from_config`, but when `namespace` is given, only the
+    /// sub-table under that key is parsed as a scout config. This lets the
+    /// repo/branch schema live under e.g. a `[scout]` table inside a
+    /// shared project config instead of owning the whole file.
+    pub fn from_config_namespaced(cfgfile_path: &str,
+                                   namespace: Option<&str>) -> Result<Context, Error> {
+        let value = try!(source::parse_file(cfgfile_path));
+
+        let value = match namespace {
+            None => value,
+            Some(ns) => {
+                let mut table = match value {
+                    Value::Table(t) => t,
+                    _ => throw_err!("config file should contain a table"),
+                };
+                let sub = match table.remove(ns) {
+                    Some(v) => v,
+                    None => throw_err!("missing namespace `{}`", ns),
+                };
+                if sub.as_table().is_none() {
+                    throw_err!("namespace `{}` should be a table", ns);
+                }
+                sub
+            }
+        };
+
+        Context::from_value(value)
+    }
 
-        Context::from_toml(&table)
+    /// Loads every file in `paths` into the neutral value tree and
+    /// deep-merges them in order (later files win) before running the
+    /// structural validation once on the merged result, so inheritance
+    /// across files (e.g. a branch defined in a base file, inherited from
+    /// in an overlay) resolves correctly.
+    pub fn from_configs(paths: &[&str]) -> Result<Context, Error> {
+        let mut merged = Value::Table(HashMap::new());
+        for path in paths {
+            let value = try!(source::parse_file(path));
+            merged = source::merge(merged, value);
+        }
+
+        Context::from_value(merged)
+    }
+
+    /// Like `from_config`, but lets environment variables under `prefix`
+    /// override or extend the parsed repos/branches, e.g.
+    /// `{prefix}REPO_<repo>_BRANCH_<branch>_INHERITS=a,b,c`. Useful in CI
+    /// where the config file is static but per-run tweaks are needed.
+    pub fn from_config_with_env(cfgfile_path: &str, prefix: &str) -> Result<Context, Error> {
+        let mut ctx = try!(Context::from_config(cfgfile_path));
+
+        try!(apply_env_overrides(&mut ctx, prefix));
+
+        for (name, repo) in ctx.repos() {
+            try!(check_inheritance_cycles(repo, name));
+        }
+
+        Ok(ctx)
     }
 }
 
@@ -173,17 +311,41 @@ impl Context {
 mod test {
     extern crate toml;
     use super::*;
-    use super::FromToml;
     use core::Error;
-    use git::{Repo, Context};
+    use git::Context;
+    use source;
 
-    use std::fmt::Debug;
+    use std::fs;
+    use std::io::Write;
 
-    fn test_err_from_toml_string<T>(toml: &str, expected: &str)
-        where T: FromToml + Debug
-    {
+    fn err_message<T>(res: Result<T, Error>) -> String {
+        match res {
+            Ok(_) => panic!("expected an error"),
+            Err(Error::TomlError(e)) => e,
+            Err(Error::StructuralError(e)) => e,
+        }
+    }
+
+    fn test_branch_err(toml: &str, expected: &str) {
+        let table = toml::Parser::new(toml).parse().unwrap();
+        let value = source::toml_to_value(&toml::Value::Table(table));
+        let res: Result<ParsedBranch, Error> = source::from_value(value);
+
+        assert_eq!(err_message(res), expected);
+    }
+
+    fn test_repo_err(toml: &str, expected: &str) {
         let table = toml::Parser::new(toml).parse().unwrap();
-        let res = T::from_toml(&table);
+        let value = source::toml_to_value(&toml::Value::Table(table));
+        let res: Result<RawRepo, Error> = source::from_value(value);
+
+        assert_eq!(err_message(res), expected);
+    }
+
+    fn test_err_from_toml_string(toml: &str, expected: &str) {
+        let table = toml::Parser::new(toml).parse().unwrap();
+        let value = source::toml_to_value(&toml::Value::Table(table));
+        let res = Context::from_value(value);
 
         match res.unwrap_err() {
             Error::TomlError(e) => assert_eq!(e, expected),
@@ -195,48 +357,49 @@ mod test {
     fn test_branch_from_toml() {
         let mut toml = String::from("");
 
-        test_err_from_toml_string::<ParsedBranch>(&toml,
-            "table `branch` should have a `name` attribute");
+        test_branch_err(&toml, "missing field `name`");
 
         toml.push_str("name = \"pnl\"\n");
         toml.push_str("inherits = 5\n");
-        test_err_from_toml_string::<ParsedBranch>(&toml,
-            "value `inherits` should be an array");
+        test_branch_err(&toml, "invalid type: integer `5`, expected a sequence");
 
         toml = String::from("name = \"pnl\"\n");
         toml.push_str("inherits = [5]\n");
-        test_err_from_toml_string::<ParsedBranch>(&toml,
-            "`inherits` values should be strings");
+        test_branch_err(&toml, "invalid type: integer `5`, expected a string");
     }
 
     #[test]
     fn test_repo_from_toml() {
         let mut toml = String::from("");
 
-        test_err_from_toml_string::<Repo>(&toml,
-            "table `repo` should have a `name` attribute");
+        test_repo_err(&toml, "missing field `name`");
 
         toml.push_str("name = \"cr\"\n");
-        test_err_from_toml_string::<Repo>(&toml,
-            "table `repo` should have an array `branch`");
+        test_repo_err(&toml, "missing field `branch`");
 
         toml.push_str("branch = [1]\n");
-        test_err_from_toml_string::<Repo>(&toml,
-            "value `branch` should be a table");
+        test_repo_err(&toml, "invalid type: integer `1`, expected struct ParsedBranch");
     }
 
     #[test]
     fn test_ctx_from_toml() {
-        test_err_from_toml_string::<Context>("",
-            "value `repo` should be an array");
+        test_err_from_toml_string("",
+            "missing field `repo`");
 
         let toml = "repo = 3";
-        test_err_from_toml_string::<Context>(&toml,
-            "value `repo` should be an array");
+        test_err_from_toml_string(&toml,
+            "invalid type: integer `3`, expected a sequence");
 
         let toml = "repo = [3]";
-        test_err_from_toml_string::<Context>(&toml,
-            "value `repo` should be a table");
+        test_err_from_toml_string(&toml,
+            "invalid type: integer `3`, expected struct RawRepo");
+
+        let toml = r#"
+            [[repo]]
+            name = "a"
+        "#;
+        test_err_from_toml_string(&toml,
+            "missing field `branch`");
 
         let toml = r#"
             [[repo]]
@@ -246,7 +409,7 @@ mod test {
             name = "b"
             inherits = ["b"]
         "#;
-        test_err_from_toml_string::<Context>(&toml,
+        test_err_from_toml_string(&toml,
             "branch `b` in repo `a` cannot inherit from itself");
 
         let toml = r#"
@@ -257,8 +420,262 @@ mod test {
             name = "b"
             inherits = ["c"]
         "#;
-        test_err_from_toml_string::<Context>(&toml,
+        test_err_from_toml_string(&toml,
             "unknown branch `c` in repo `a`");
+
+        let toml = r#"
+            [[repo]]
+            name = "a"
+
+            [[repo.branch]]
+            name = "b"
+            inherits = ["c"]
+
+            [[repo.branch]]
+            name = "c"
+            inherits = ["d"]
+
+            [[repo.branch]]
+            name = "d"
+            inherits = ["b"]
+        "#;
+        test_err_from_toml_string(&toml,
+            "inheritance cycle in repo `a`: b -> c -> d -> b");
+    }
+
+    fn write_temp_file(name: &str, ext: &str, content: &str) -> String {
+        let path = ::std::env::temp_dir().join(format!("scout_test_{}.{}", name, ext));
+        let path = path.to_str().unwrap().to_string();
+
+        let mut f = fs::File::create(&path).unwrap();
+        f.write_all(content.as_bytes()).unwrap();
+
+        path
+    }
+
+    fn write_temp_toml(name: &str, content: &str) -> String {
+        write_temp_file(name, "toml", content)
+    }
+
+    #[test]
+    fn test_from_config_json() {
+        let path = write_temp_file("format_json", "json", r#"{
+            "repo": [
+                {"name": "r", "branch": [{"name": "master"}]}
+            ]
+        }"#);
+
+        let ctx = Context::from_config(&path).unwrap();
+        assert!(ctx.find_repo("r").unwrap().find_branch("master").is_some());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_from_config_yaml() {
+        let path = write_temp_file("format_yaml", "yaml",
+            "repo:\n  - name: r\n    branch:\n      - name: master\n");
+
+        let ctx = Context::from_config(&path).unwrap();
+        assert!(ctx.find_repo("r").unwrap().find_branch("master").is_some());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_from_config_with_env_creates_missing_branch() {
+        let path = write_temp_toml("env_create_branch", r#"
+            [[repo]]
+            name = "r"
+
+            [[repo.branch]]
+            name = "master"
+        "#);
+
+        env::set_var("SCOUT_TEST1_REPO_r_BRANCH_feature_INHERITS", "master");
+
+        let ctx = Context::from_config_with_env(&path, "SCOUT_TEST1_").unwrap();
+        let repo = ctx.find_repo("r").unwrap();
+        let branch = repo.find_branch("feature").unwrap().borrow();
+
+        assert_eq!(branch.inherits().len(), 1);
+        assert_eq!(branch.inherits()[0].borrow().name(), "master");
+
+        env::remove_var("SCOUT_TEST1_REPO_r_BRANCH_feature_INHERITS");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_from_config_with_env_unknown_repo() {
+        let path = write_temp_toml("env_unknown_repo", r#"
+            [[repo]]
+            name = "r"
+
+            [[repo.branch]]
+            name = "master"
+        "#);
+
+        env::set_var("SCOUT_TEST2_REPO_other_BRANCH_feature_INHERITS", "master");
+
+        let res = Context::from_config_with_env(&path, "SCOUT_TEST2_");
+        assert_eq!(err_message(res),
+                   "unknown repo `other` in environment override \
+                    `SCOUT_TEST2_REPO_other_BRANCH_feature_INHERITS`");
+
+        env::remove_var("SCOUT_TEST2_REPO_other_BRANCH_feature_INHERITS");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_from_config_with_env_detects_cycle() {
+        let path = write_temp_toml("env_cycle", r#"
+            [[repo]]
+            name = "r"
+
+            [[repo.branch]]
+            name = "a"
+        "#);
+
+        env::set_var("SCOUT_TEST3_REPO_r_BRANCH_a_INHERITS", "a");
+
+        let res = Context::from_config_with_env(&path, "SCOUT_TEST3_");
+        assert_eq!(err_message(res), "inheritance cycle in repo `r`: a -> a");
+
+        env::remove_var("SCOUT_TEST3_REPO_r_BRANCH_a_INHERITS");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_from_config_namespaced_missing_namespace() {
+        let path = write_temp_toml("namespace_missing", "other = 1\n");
+
+        let res = Context::from_config_namespaced(&path, Some("scout"));
+        assert_eq!(err_message(res), "missing namespace `scout`");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_from_config_namespaced_not_a_table() {
+        let path = write_temp_toml("namespace_not_table", "scout = 1\n");
+
+        let res = Context::from_config_namespaced(&path, Some("scout"));
+        assert_eq!(err_message(res), "namespace `scout` should be a table");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_from_config_namespaced_happy_path() {
+        let path = write_temp_toml("namespace_happy", r#"
+            [other]
+            unrelated = true
+
+            [[scout.repo]]
+            name = "r"
+
+            [[scout.repo.branch]]
+            name = "master"
+        "#);
+
+        let ctx = Context::from_config_namespaced(&path, Some("scout")).unwrap();
+        assert!(ctx.find_repo("r").unwrap().find_branch("master").is_some());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_from_configs_adds_branch_via_overlay() {
+        let base = write_temp_toml("configs_base_add", r#"
+            [[repo]]
+            name = "r"
+
+            [[repo.branch]]
+            name = "master"
+        "#);
+        let overlay = write_temp_toml("configs_overlay_add", r#"
+            [[repo]]
+            name = "r"
+
+            [[repo.branch]]
+            name = "feature"
+            inherits = ["master"]
+        "#);
+
+        let ctx = Context::from_configs(&[&base, &overlay]).unwrap();
+        let repo = ctx.find_repo("r").unwrap();
+
+        assert!(repo.find_branch("master").is_some());
+        let feature = repo.find_branch("feature").unwrap().borrow();
+        assert_eq!(feature.inherits()[0].borrow().name(), "master");
+
+        fs::remove_file(&base).unwrap();
+        fs::remove_file(&overlay).unwrap();
+    }
+
+    #[test]
+    fn test_from_configs_overlay_replaces_inherits() {
+        let base = write_temp_toml("configs_base_replace", r#"
+            [[repo]]
+            name = "r"
+
+            [[repo.branch]]
+            name = "master"
+
+            [[repo.branch]]
+            name = "other"
+
+            [[repo.branch]]
+            name = "feature"
+            inherits = ["master"]
+        "#);
+        let overlay = write_temp_toml("configs_overlay_replace", r#"
+            [[repo]]
+            name = "r"
+
+            [[repo.branch]]
+            name = "feature"
+            inherits = ["other"]
+        "#);
+
+        let ctx = Context::from_configs(&[&base, &overlay]).unwrap();
+        let repo = ctx.find_repo("r").unwrap();
+        let feature = repo.find_branch("feature").unwrap().borrow();
+
+        assert_eq!(feature.inherits().len(), 1);
+        assert_eq!(feature.inherits()[0].borrow().name(), "other");
+
+        fs::remove_file(&base).unwrap();
+        fs::remove_file(&overlay).unwrap();
+    }
+
+    #[test]
+    fn test_from_configs_cross_file_inheritance() {
+        let base = write_temp_toml("configs_base_cross", r#"
+            [[repo]]
+            name = "r"
+
+            [[repo.branch]]
+            name = "master"
+        "#);
+        let overlay = write_temp_toml("configs_overlay_cross", r#"
+            [[repo]]
+            name = "r"
+
+            [[repo.branch]]
+            name = "feature"
+            inherits = ["master"]
+        "#);
+
+        let ctx = Context::from_configs(&[&base, &overlay]).unwrap();
+        let repo = ctx.find_repo("r").unwrap();
+        let ancestors = repo.find_branch("feature").unwrap().borrow().resolved_ancestors();
+
+        assert_eq!(ancestors.len(), 1);
+        assert_eq!(ancestors[0].borrow().name(), "master");
+
+        fs::remove_file(&base).unwrap();
+        fs::remove_file(&overlay).unwrap();
     }
 }
 
@@ -0,0 +1,422 @@
+extern crate toml;
+extern crate rustc_serialize;
+extern crate yaml_rust;
+extern crate serde;
+
+use self::rustc_serialize::json::Json;
+use self::yaml_rust::{Yaml, YamlLoader};
+use self::serde::de;
+use self::serde::forward_to_deserialize_any;
+
+use core::Error;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+// {{{ Value
+
+/// A format-neutral value tree. Every supported config format is parsed
+/// into this before the rest of the parser looks at it, so `ParsedBranch`,
+/// `ParsedRepo` and `Context` only ever have to know about one shape of
+/// data regardless of whether it came from TOML, JSON or YAML.
+#[derive(Debug, Clone)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Bool(bool),
+    Array(Vec<Value>),
+    Table(HashMap<String, Value>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_slice(&self) -> Option<&[Value]> {
+        match *self {
+            Value::Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&HashMap<String, Value>> {
+        match *self {
+            Value::Table(ref t) => Some(t),
+            _ => None,
+        }
+    }
+}
+
+// }}}
+// {{{ TOML
+
+pub(crate) fn toml_to_value(v: &toml::Value) -> Value {
+    match *v {
+        toml::Value::String(ref s) => Value::String(s.clone()),
+        toml::Value::Integer(i) => Value::Integer(i),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Array(ref a) => {
+            Value::Array(a.iter().map(toml_to_value).collect())
+        }
+        toml::Value::Table(ref t) => {
+            Value::Table(t.iter()
+                          .map(|(k, v)| (k.clone(), toml_to_value(v)))
+                          .collect())
+        }
+        // floats and datetimes aren't part of scout's schema
+        _ => Value::Table(HashMap::new()),
+    }
+}
+
+fn parse_toml(content: &str) -> Result<Value, Error> {
+    let mut parser = toml::Parser::new(content);
+    match parser.parse() {
+        Some(table) => Ok(toml_to_value(&toml::Value::Table(table))),
+        None => Err(Error::TomlError(format!("error while parsing toml: {:?}",
+                                              parser.errors))),
+    }
+}
+
+// }}}
+// {{{ JSON
+
+fn json_to_value(v: &Json) -> Value {
+    match *v {
+        Json::String(ref s) => Value::String(s.clone()),
+        Json::I64(i) => Value::Integer(i),
+        Json::U64(u) => Value::Integer(u as i64),
+        Json::Boolean(b) => Value::Bool(b),
+        Json::Array(ref a) => Value::Array(a.iter().map(json_to_value).collect()),
+        Json::Object(ref o) => {
+            Value::Table(o.iter()
+                          .map(|(k, v)| (k.clone(), json_to_value(v)))
+                          .collect())
+        }
+        _ => Value::Table(HashMap::new()),
+    }
+}
+
+fn parse_json(content: &str) -> Result<Value, Error> {
+    match Json::from_str(content) {
+        Ok(json) => Ok(json_to_value(&json)),
+        Err(e) => Err(Error::TomlError(format!("error while parsing json: {}", e))),
+    }
+}
+
+// }}}
+// {{{ YAML
+
+fn yaml_to_value(v: &Yaml) -> Value {
+    match *v {
+        Yaml::String(ref s) => Value::String(s.clone()),
+        Yaml::Integer(i) => Value::Integer(i),
+        Yaml::Boolean(b) => Value::Bool(b),
+        Yaml::Array(ref a) => Value::Array(a.iter().map(yaml_to_value).collect()),
+        Yaml::Hash(ref h) => {
+            let mut table = HashMap::new();
+            for (k, v) in h {
+                if let Some(key) = k.as_str() {
+                    table.insert(key.to_string(), yaml_to_value(v));
+                }
+            }
+            Value::Table(table)
+        }
+        _ => Value::Table(HashMap::new()),
+    }
+}
+
+fn parse_yaml(content: &str) -> Result<Value, Error> {
+    match YamlLoader::load_from_str(content) {
+        Ok(ref docs) if !docs.is_empty() => Ok(yaml_to_value(&docs[0])),
+        Ok(_) => Ok(Value::Table(HashMap::new())),
+        Err(e) => Err(Error::TomlError(format!("error while parsing yaml: {}", e))),
+    }
+}
+
+// }}}
+
+// {{{ Merging
+
+fn array_name(item: &Value) -> Option<&str> {
+    item.as_table().and_then(|t| t.get("name")).and_then(|v| v.as_str())
+}
+
+/// An array counts as "named" when every element is a table with a `name`
+/// attribute (e.g. the `repo` and `branch` arrays), as opposed to a plain
+/// scalar array (e.g. `inherits`).
+fn is_named_array(items: &[Value]) -> bool {
+    !items.is_empty() && items.iter().all(|v| array_name(v).is_some())
+}
+
+fn merge_named_arrays(base: Vec<Value>, overlay: Vec<Value>) -> Vec<Value> {
+    let mut result = base;
+
+    for item in overlay {
+        let existing = array_name(&item).and_then(|name| {
+            result.iter().position(|v| array_name(v) == Some(name))
+        });
+        match existing {
+            Some(idx) => {
+                let merged = merge(result[idx].clone(), item);
+                result[idx] = merged;
+            }
+            None => result.push(item),
+        }
+    }
+
+    result
+}
+
+/// Deep-merges `overlay` into `base`: tables are merged key by key
+/// recursively, arrays of named tables (matched by their `name` attribute)
+/// are merged element by element so a later file can add to or replace a
+/// single entry, and anything else (scalars, plain arrays) is simply
+/// overridden by `overlay`.
+pub fn merge(base: Value, overlay: Value) -> Value {
+    match (base, overlay) {
+        (Value::Table(mut base), Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                let merged = match base.remove(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            Value::Table(base)
+        }
+        (Value::Array(base), Value::Array(overlay)) => {
+            if is_named_array(&base) || is_named_array(&overlay) {
+                Value::Array(merge_named_arrays(base, overlay))
+            } else {
+                Value::Array(overlay)
+            }
+        }
+        (_, overlay) => overlay,
+    }
+}
+
+// }}}
+
+// {{{ serde Deserializer
+
+/// Error produced while deserializing a `Value` tree into a parsed struct.
+/// Kept distinct from the crate's `Error` so `de::Error::custom` has
+/// somewhere to put messages coming from serde itself; callers map it back
+/// to `Error::TomlError`.
+#[derive(Debug)]
+pub struct ValueError(String);
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl ::std::error::Error for ValueError {
+    fn description(&self) -> &str {
+        &self.0
+    }
+}
+
+impl de::Error for ValueError {
+    fn custom<T: fmt::Display>(msg: T) -> ValueError {
+        ValueError(msg.to_string())
+    }
+}
+
+struct SeqDeserializer {
+    iter: ::std::vec::IntoIter<Value>,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = ValueError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, ValueError>
+        where T: de::DeserializeSeed<'de>
+    {
+        match self.iter.next() {
+            Some(v) => seed.deserialize(v).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+struct MapDeserializer {
+    iter: ::std::collections::hash_map::IntoIter<String, Value>,
+    value: Option<Value>,
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+    type Error = ValueError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, ValueError>
+        where K: de::DeserializeSeed<'de>
+    {
+        match self.iter.next() {
+            Some((k, v)) => {
+                self.value = Some(v);
+                seed.deserialize(Value::String(k)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, ValueError>
+        where V: de::DeserializeSeed<'de>
+    {
+        match self.value.take() {
+            Some(v) => seed.deserialize(v),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = ValueError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, ValueError>
+        where V: de::Visitor<'de>
+    {
+        match self {
+            Value::String(s) => visitor.visit_string(s),
+            Value::Integer(i) => visitor.visit_i64(i),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Array(a) => {
+                visitor.visit_seq(SeqDeserializer { iter: a.into_iter() })
+            }
+            Value::Table(t) => {
+                visitor.visit_map(MapDeserializer { iter: t.into_iter(), value: None })
+            }
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Deserializes a `Value` tree directly into `T`, translating serde's
+/// deserialization errors into the crate's own `Error::TomlError`.
+pub fn from_value<T: de::DeserializeOwned>(value: Value) -> Result<T, Error> {
+    T::deserialize(value).map_err(|e| Error::TomlError(e.to_string()))
+}
+
+// }}}
+
+/// Parses `path` into a `Value` tree, picking the format from the file's
+/// extension (`.toml`, `.json`, `.yaml`/`.yml`).
+pub fn parse_file(path: &str) -> Result<Value, Error> {
+    let mut f = match fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) => return Err(Error::TomlError(format!("error while opening `{}`: {}",
+                                                        path, e))),
+    };
+    let mut buf = String::new();
+    if let Err(e) = f.read_to_string(&mut buf) {
+        return Err(Error::TomlError(format!("error while reading `{}`: {}", path, e)));
+    }
+
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("toml") => parse_toml(&buf),
+        Some("json") => parse_json(&buf),
+        Some("yaml") | Some("yml") => parse_yaml(&buf),
+        other => Err(Error::TomlError(format!("unsupported config format `{:?}` for `{}`",
+                                               other, path))),
+    }
+}
+
+// {{{ Tests
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn table(pairs: Vec<(&str, Value)>) -> Value {
+        let mut t = HashMap::new();
+        for (k, v) in pairs {
+            t.insert(k.to_string(), v);
+        }
+        Value::Table(t)
+    }
+
+    fn names(array: &Value) -> Vec<&str> {
+        array.as_slice().unwrap().iter()
+             .map(|v| v.as_table().unwrap().get("name").unwrap().as_str().unwrap())
+             .collect()
+    }
+
+    #[test]
+    fn test_merge_named_array_adds_new_entry() {
+        let base = table(vec![("repo", Value::Array(vec![
+            table(vec![("name", Value::String("a".to_string()))]),
+        ]))]);
+        let overlay = table(vec![("repo", Value::Array(vec![
+            table(vec![("name", Value::String("b".to_string()))]),
+        ]))]);
+
+        let merged = merge(base, overlay);
+        let repo = merged.as_table().unwrap().get("repo").unwrap();
+
+        assert_eq!(names(repo), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_merge_named_array_merges_existing_entry_by_name() {
+        let base = table(vec![("repo", Value::Array(vec![
+            table(vec![("name", Value::String("a".to_string())),
+                       ("branch", Value::Array(vec![
+                           table(vec![("name", Value::String("master".to_string()))]),
+                       ]))]),
+        ]))]);
+        let overlay = table(vec![("repo", Value::Array(vec![
+            table(vec![("name", Value::String("a".to_string())),
+                       ("branch", Value::Array(vec![
+                           table(vec![("name", Value::String("feature".to_string()))]),
+                       ]))]),
+        ]))]);
+
+        let merged = merge(base, overlay);
+        let repo = merged.as_table().unwrap().get("repo").unwrap();
+
+        assert_eq!(names(repo), vec!["a"]);
+        let branch = repo.as_slice().unwrap()[0].as_table().unwrap().get("branch").unwrap();
+        assert_eq!(names(branch), vec!["master", "feature"]);
+    }
+
+    #[test]
+    fn test_merge_scalar_array_overlay_wins() {
+        let base = table(vec![("inherits", Value::Array(vec![
+            Value::String("a".to_string()),
+        ]))]);
+        let overlay = table(vec![("inherits", Value::Array(vec![
+            Value::String("b".to_string()), Value::String("c".to_string()),
+        ]))]);
+
+        let merged = merge(base, overlay);
+        let inherits = merged.as_table().unwrap().get("inherits").unwrap().as_slice().unwrap();
+        let values: Vec<&str> = inherits.iter().map(|v| v.as_str().unwrap()).collect();
+
+        assert_eq!(values, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_merge_scalar_overlay_wins() {
+        let base = table(vec![("name", Value::String("a".to_string()))]);
+        let overlay = table(vec![("name", Value::String("b".to_string()))]);
+
+        let merged = merge(base, overlay);
+
+        assert_eq!(merged.as_table().unwrap().get("name").unwrap().as_str(), Some("b"));
+    }
+}
+
+// }}}